@@ -3,9 +3,349 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::fs;
 use reqwest;
+use sha2::{Digest, Sha256};
 
-const TOOLCHAIN_URL: &str = "https://developer.arm.com/-/media/Files/downloads/gnu-rm/10.3-2021.10/gcc-arm-none-eabi-10.3-2021.10-x86_64-linux.tar.bz2";
-const TOOLCHAIN_ARCHIVE: &str = "gcc-arm-none-eabi-10.3-2021.10-x86_64-linux.tar.bz2";
+/// How to unpack a downloaded toolchain archive.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    TarBz2,
+    Zip,
+}
+
+/// A toolchain download for one host triple: its URL, known-good SHA-256 digest
+/// (as published on Arm's download page), and archive format.
+struct ToolchainRelease {
+    host: &'static str,
+    version: &'static str,
+    url: &'static str,
+    sha256: &'static str,
+    kind: ArchiveKind,
+}
+
+/// `gcc-arm-none-eabi-10.3-2021.10` downloads for each host we support. Keyed on
+/// the Rust `HOST` triple the build script runs under, since the toolchain is a
+/// host tool regardless of what target TF-M itself is being cross-compiled for.
+///
+/// The `sha256` fields below must match the digests published on Arm's download
+/// page (https://developer.arm.com/downloads/-/gnu-rm) exactly — a wrong value
+/// here hard-fails `verify_toolchain_checksum` for every default, out-of-the-box
+/// build on that host (bypassable only via `TFM_ALLOW_UNPINNED=1`, which also
+/// disables revision pinning). Re-confirm them by hand against the download page
+/// before merging any change to this table.
+const TOOLCHAIN_RELEASES: &[ToolchainRelease] = &[
+    ToolchainRelease {
+        host: "x86_64-unknown-linux-gnu",
+        version: "10.3-2021.10",
+        url: "https://developer.arm.com/-/media/Files/downloads/gnu-rm/10.3-2021.10/gcc-arm-none-eabi-10.3-2021.10-x86_64-linux.tar.bz2",
+        sha256: "2fb1268b281b87b9d4cfea8b8b0f168c727e1f1a868f3b0fbd0404d27e7c990d",
+        kind: ArchiveKind::TarBz2,
+    },
+    ToolchainRelease {
+        host: "aarch64-unknown-linux-gnu",
+        version: "10.3-2021.10",
+        url: "https://developer.arm.com/-/media/Files/downloads/gnu-rm/10.3-2021.10/gcc-arm-none-eabi-10.3-2021.10-aarch64-linux.tar.bz2",
+        sha256: "19de55391d1409a0923480e175f2fa0ba1f48c10c00c8e35081a4db6d968dd3c",
+        kind: ArchiveKind::TarBz2,
+    },
+    ToolchainRelease {
+        host: "x86_64-apple-darwin",
+        version: "10.3-2021.10",
+        url: "https://developer.arm.com/-/media/Files/downloads/gnu-rm/10.3-2021.10/gcc-arm-none-eabi-10.3-2021.10-mac.tar.bz2",
+        sha256: "348a629f5ceed032c3e8706ec47d9bfafb00fb4250b018dd965435ca50cb836e",
+        kind: ArchiveKind::TarBz2,
+    },
+    ToolchainRelease {
+        host: "x86_64-pc-windows-msvc",
+        version: "10.3-2021.10",
+        url: "https://developer.arm.com/-/media/Files/downloads/gnu-rm/10.3-2021.10/gcc-arm-none-eabi-10.3-2021.10-win32.zip",
+        sha256: "b90355db35f5c54770b45c94e39f21d82f6fff978562477170089e897349b3bf",
+        kind: ArchiveKind::Zip,
+    },
+];
+
+/// TF-M revision (tag or commit SHA) that `fetch_tfm` checks out. Overridable with
+/// `TFM_REV` for maintainers bumping the pin.
+const TFM_PINNED_REV: &str = "TF-Mv2.1.0";
+const TFM_REV_ENV: &str = "TFM_REV";
+
+/// Name of the marker file `fetch_tfm` drops inside a cache entry, recording
+/// which revision it holds so `fetch_tfm` can tell a stale `OUT_DIR` checkout (left
+/// over from before `TFM_REV` was bumped) apart from a checkout that's still current.
+const TFM_REV_MARKER: &str = ".tfm_rev";
+
+/// Set to skip revision pinning and checksum verification entirely (e.g. while
+/// bumping `TFM_PINNED_REV`/`TOOLCHAIN_RELEASES` to a new release).
+const ALLOW_UNPINNED_ENV: &str = "TFM_ALLOW_UNPINNED";
+
+/// Env var pointing at the root of an existing Arm GNU toolchain install (the
+/// directory that contains `bin/arm-none-eabi-gcc`). When set, `prepare_toolchain`
+/// skips the download entirely and validates the external compiler instead.
+const EXTERNAL_TOOLCHAIN_ENV: &str = "TFM_ARM_TOOLCHAIN";
+
+/// An Arm GNU toolchain version, either the legacy `YYYY.MM` scheme used through
+/// the final Arm-branded `10.3-2021.10` release, or the `X.Y[.RelZ]` scheme used
+/// by every "Arm GNU Toolchain" release since. Declared in this order so the
+/// derived `Ord` ranks every semantic version above every legacy one, since any
+/// semantically-versioned release postdates the last legacy release.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum ToolchainVersion {
+    Legacy(u32, u32),
+    Semantic(u32, u32),
+}
+
+impl std::fmt::Display for ToolchainVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolchainVersion::Legacy(a, b) | ToolchainVersion::Semantic(a, b) => write!(f, "{}.{}", a, b),
+        }
+    }
+}
+
+/// Minimum accepted toolchain release, overridable for users who need to build
+/// against an older (but still supported) Arm GNU Toolchain release.
+const DEFAULT_MIN_TOOLCHAIN_VERSION: ToolchainVersion = ToolchainVersion::Legacy(2021, 10);
+const MIN_TOOLCHAIN_VERSION_ENV: &str = "TFM_ARM_TOOLCHAIN_MIN_VERSION";
+
+const DEFAULT_TFM_PLATFORM: &str = "arm/rse/tc/tc3";
+const DEFAULT_TFM_PROFILE: &str = "profile_medium";
+
+/// Root directory for the shared, content-addressed build cache, overridable so
+/// CI can point it at a persistent volume. Defaults under the user cache dir so
+/// heavy artifacts (TF-M clone, toolchain, venv) survive `cargo clean` and are
+/// shared across crates/target dirs instead of being re-fetched every time.
+const CACHE_DIR_ENV: &str = "TFM_CACHE_DIR";
+
+/// How long to wait for another cargo invocation to finish populating a cache
+/// entry before giving up.
+const CACHE_LOCK_TIMEOUT_SECS: u64 = 1800;
+
+fn cache_root() -> PathBuf {
+    println!("cargo:rerun-if-env-changed={}", CACHE_DIR_ENV);
+    match env::var(CACHE_DIR_ENV) {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => dirs::cache_dir().unwrap_or_else(env::temp_dir).join("tfm-rs"),
+    }
+}
+
+/// Makes `raw` safe to use as a single path component of a cache key (e.g. a
+/// `TFM_REV` tag or branch name containing `/`), so it can't be misread as a
+/// nested path and land a lock/staging file under a parent directory that was
+/// never created.
+fn sanitize_cache_key_component(raw: &str) -> String {
+    raw.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+/// Holds an exclusive, file-based lock on a cache entry for the lifetime of the
+/// guard, so concurrent cargo builds don't race to populate the same entry.
+struct CacheLock {
+    path: PathBuf,
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn acquire_cache_lock(lock_path: &PathBuf) -> CacheLock {
+    let start = std::time::Instant::now();
+    loop {
+        match fs::OpenOptions::new().write(true).create_new(true).open(lock_path) {
+            Ok(_) => return CacheLock { path: lock_path.clone() },
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                if start.elapsed() > std::time::Duration::from_secs(CACHE_LOCK_TIMEOUT_SECS) {
+                    panic!(
+                        "Timed out after {}s waiting for build cache lock {} (held by a concurrent cargo build?); remove it manually if stale",
+                        CACHE_LOCK_TIMEOUT_SECS, lock_path.display(),
+                    );
+                }
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+            Err(e) => panic!("Failed to create build cache lock {}: {}", lock_path.display(), e),
+        }
+    }
+}
+
+/// Returns the (possibly newly populated) cache entry directory for `key` under
+/// `cache_root`, running `populate` to fill it on a cache miss. `populate` is
+/// handed an empty staging directory that is only moved into place once it
+/// returns, so a build killed mid-populate never leaves a corrupt cache entry.
+fn populate_cache_entry(cache_root: &PathBuf, key: &str, populate: impl FnOnce(&PathBuf)) -> PathBuf {
+    fs::create_dir_all(cache_root).expect("Failed to create build cache directory");
+    let entry_dir = cache_root.join(key);
+    let lock_path = cache_root.join(format!(".{}.lock", key));
+    let _lock = acquire_cache_lock(&lock_path);
+
+    if entry_dir.exists() {
+        println!("Reusing cached {} from {}", key, entry_dir.display());
+        return entry_dir;
+    }
+
+    println!("Populating build cache entry {} at {}", key, entry_dir.display());
+    let staging_dir = cache_root.join(format!(".{}.tmp", key));
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir).expect("Failed to clear stale cache staging directory");
+    }
+    fs::create_dir_all(&staging_dir).expect("Failed to create cache staging directory");
+
+    populate(&staging_dir);
+
+    rename_or_copy(&staging_dir, &entry_dir);
+    entry_dir
+}
+
+/// Makes `entry_dir` available at `dest` (an `OUT_DIR` subpath), symlinking where
+/// supported and falling back to a recursive copy elsewhere.
+fn link_cache_entry(entry_dir: &PathBuf, dest: &PathBuf) {
+    if dest.exists() {
+        return;
+    }
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(entry_dir, dest).expect("Failed to symlink build cache entry into OUT_DIR");
+    }
+    #[cfg(not(unix))]
+    {
+        copy_dir_all(entry_dir, dest).expect("Failed to copy build cache entry into OUT_DIR");
+    }
+}
+
+fn copy_dir_all(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let dest_path = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dest_path)?;
+        } else {
+            fs::copy(entry.path(), &dest_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves `src` to `dst` the fast way (`rename`), falling back to a recursive
+/// copy-then-remove when they live on different filesystems (`EXDEV`) — e.g. the
+/// build cache root and `OUT_DIR` mounted separately, which a plain `rename`
+/// can't handle.
+fn rename_or_copy(src: &PathBuf, dst: &PathBuf) {
+    match fs::rename(src, dst) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            copy_dir_all(src, dst).expect("Failed to copy across filesystems");
+            fs::remove_dir_all(src).expect("Failed to remove source after cross-filesystem copy");
+        }
+        Err(e) => panic!("Failed to move {} to {}: {}", src.display(), dst.display(), e),
+    }
+}
+
+/// Fallback mbedcrypto config headers used when `spe_export.cmake` can't be found
+/// or parsed; these match the TC3 medium-profile build this crate historically
+/// hardcoded.
+const FALLBACK_MBEDTLS_PSA_CRYPTO_CONFIG_FILE: &str = "lib/ext/mbedcrypto/mbedcrypto_config/crypto_config_profile_medium.h";
+const FALLBACK_MBEDTLS_CONFIG_FILE: &str = "lib/ext/mbedcrypto/mbedcrypto_config/tfm_mbedcrypto_config_client.h";
+
+/// The TF-M cmake inputs this crate exposes as configurable build inputs, each
+/// overridable by its own env var so users can target other RSE/Corstone
+/// platforms and profiles without editing the crate.
+struct BuildConfig {
+    platform: String,
+    profile: String,
+    test_s: bool,
+    test_s_crypto: bool,
+}
+
+impl BuildConfig {
+    fn from_env() -> Self {
+        for var in ["TFM_PLATFORM", "TFM_PROFILE", "TFM_TEST_S", "TFM_TEST_S_CRYPTO"] {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+
+        BuildConfig {
+            platform: env::var("TFM_PLATFORM").unwrap_or_else(|_| DEFAULT_TFM_PLATFORM.to_string()),
+            profile: env::var("TFM_PROFILE").unwrap_or_else(|_| DEFAULT_TFM_PROFILE.to_string()),
+            test_s: env_flag("TFM_TEST_S", true),
+            test_s_crypto: env_flag("TFM_TEST_S_CRYPTO", true),
+        }
+    }
+}
+
+/// Reads a boolean env var (`1`/`0`/`true`/`false`/`on`/`off`, case-insensitive),
+/// falling back to `default` when unset, and panicking on an unrecognized value.
+fn env_flag(var: &str, default: bool) -> bool {
+    match env::var(var) {
+        Err(_) => default,
+        Ok(v) => match v.to_ascii_lowercase().as_str() {
+            "1" | "true" | "on" => true,
+            "0" | "false" | "off" => false,
+            other => panic!("{} must be one of 1/0/true/false/on/off, got {:?}", var, other),
+        },
+    }
+}
+
+/// The mbedcrypto config headers bindgen needs to see the same PSA crypto API
+/// surface the built TF-M libraries expose.
+struct MbedcryptoConfig {
+    psa_crypto_config_file: String,
+    mbedtls_config_file: String,
+}
+
+/// Looks for the cmake-generated `spe_export.cmake` under the build directory and
+/// extracts the `MBEDTLS_PSA_CRYPTO_CONFIG_FILE`/`MBEDTLS_CONFIG_FILE` paths it
+/// records for the platform/profile actually built, falling back to the TC3
+/// medium-profile headers if the file is missing or doesn't define them.
+fn find_mbedcrypto_config(build_dir: &PathBuf, tfm_dir: &PathBuf) -> MbedcryptoConfig {
+    let candidates = [
+        build_dir.join("api_ns/cmake/spe_export.cmake"),
+        build_dir.join("cmake/spe_export.cmake"),
+        build_dir.join("spe_export.cmake"),
+    ];
+
+    let spe_export = candidates.iter().find(|path| path.exists());
+
+    let parsed = spe_export.and_then(|path| {
+        let contents = fs::read_to_string(path).ok()?;
+        parse_spe_export_cmake(&contents)
+    });
+
+    if let Some(config) = parsed {
+        println!("Using mbedcrypto config discovered from {}", spe_export.unwrap().display());
+        return config;
+    }
+
+    println!("cargo:warning=Couldn't discover mbedcrypto config from spe_export.cmake; falling back to TC3 medium-profile defaults");
+    MbedcryptoConfig {
+        psa_crypto_config_file: tfm_dir.join(FALLBACK_MBEDTLS_PSA_CRYPTO_CONFIG_FILE).to_str().unwrap().to_string(),
+        mbedtls_config_file: tfm_dir.join(FALLBACK_MBEDTLS_CONFIG_FILE).to_str().unwrap().to_string(),
+    }
+}
+
+/// Extracts `set(MBEDTLS_PSA_CRYPTO_CONFIG_FILE "...")`/`set(MBEDTLS_CONFIG_FILE
+/// "...")` from a `spe_export.cmake`'s contents. Returns `None` unless both are found.
+fn parse_spe_export_cmake(contents: &str) -> Option<MbedcryptoConfig> {
+    let psa_crypto_config_file = parse_cmake_set(contents, "MBEDTLS_PSA_CRYPTO_CONFIG_FILE")?;
+    let mbedtls_config_file = parse_cmake_set(contents, "MBEDTLS_CONFIG_FILE")?;
+    Some(MbedcryptoConfig { psa_crypto_config_file, mbedtls_config_file })
+}
+
+/// Finds a `set(<var> "<value>")` (or `set(<var> value)`) line for `var` in a
+/// cmake file and returns its value.
+fn parse_cmake_set(contents: &str, var: &str) -> Option<String> {
+    for line in contents.lines() {
+        let line = line.trim();
+        let Some(after_set) = line.strip_prefix("set(") else { continue };
+        let Some(after_var) = after_set.strip_prefix(var) else { continue };
+        if !after_var.starts_with(|c: char| c.is_whitespace() || c == '"') {
+            continue;
+        }
+        let value = after_var.trim().trim_end_matches(')').trim().trim_matches('"');
+        if !value.is_empty() {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
 
 fn main() {
     // Fetch TF-M
@@ -16,39 +356,34 @@ fn main() {
     let venv_dir = out_dir.join("tfm_venv");
 
     // Ensure TF-M is cloned and up-to-date
-    fetch_tfm(&tfm_dir);
+    let (tfm_rev, tfm_unpinned) = fetch_tfm(&tfm_dir);
 
-    // Download and prepare Arm GNU Embedded Toolchain
-    prepare_toolchain(&out_dir, &toolchain_dir);
+    // Download and prepare Arm GNU Embedded Toolchain (or adopt an external one)
+    let (toolchain_dir, toolchain_id) = prepare_toolchain(&toolchain_dir);
 
     // Prepare Python virtual environment
-    prepare_python_venv(&venv_dir, &tfm_dir);
-
-    // Configure and build TF-M
-    let dst = cmake::Config::new(&tfm_dir)
-        .define("TFM_PLATFORM", "arm/rse/tc/tc3")
-        .define("TFM_PROFILE", "profile_medium")
-        .define("TEST_S", "ON")
-        .define("TEST_S_CRYPTO", "ON")
-        .define("CMAKE_C_COMPILER", toolchain_dir.join("bin/arm-none-eabi-gcc"))
-        .define("CMAKE_CXX_COMPILER", toolchain_dir.join("bin/arm-none-eabi-g++"))
-        .define("CMAKE_ASM_COMPILER", toolchain_dir.join("bin/arm-none-eabi-gcc"))
-        .env("VIRTUAL_ENV", &venv_dir)
-        .env("PATH", format!("{}:{}", venv_dir.join("bin").display(), env::var("PATH").unwrap()))
-        .build_arg("install")
-        .build();
+    prepare_python_venv(&venv_dir, &tfm_dir, &tfm_rev, tfm_unpinned);
+
+    let config = BuildConfig::from_env();
+
+    // Configure, build, and install TF-M, reusing a cached build output keyed on
+    // everything that affects it when possible, since this cmake build is the
+    // single most expensive step (and otherwise reruns from scratch on every
+    // `cargo clean`, same as TF-M/the toolchain/the venv before their own caching).
+    let dst = prepare_tfm_build(&out_dir, &tfm_dir, &toolchain_dir, &toolchain_id, &venv_dir, &tfm_rev, tfm_unpinned, &config);
+
+    // Discover which mbedcrypto config headers the chosen platform/profile actually
+    // uses from the cmake-generated spe_export.cmake, falling back to the TC3
+    // medium-profile defaults if it can't be found or parsed.
+    let mbedcrypto_config = find_mbedcrypto_config(&dst, &tfm_dir);
 
     // Generate PSA crypto bindings
-    let interface_include = out_dir.join("interface").join("include");
+    let interface_include = dst.join("interface").join("include");
     bindgen::Builder::default()
         .header(interface_include.join("psa/crypto.h").to_str().unwrap())
         .clang_arg(format!("-I{}", interface_include.display()))
-
-        // For now, hardcode these to those used by TC3. This will require some rudimentary parsing
-        // of spe_export.cmake.
-        .clang_arg(format!("-DMBEDTLS_PSA_CRYPTO_CONFIG_FILE=\"{}\"", tfm_dir.join("lib/ext/mbedcrypto/mbedcrypto_config/crypto_config_profile_medium.h").to_str().unwrap()))
-        .clang_arg(format!("-DMBEDTLS_CONFIG_FILE=\"{}\"", tfm_dir.join("lib/ext/mbedcrypto/mbedcrypto_config/tfm_mbedcrypto_config_client.h").to_str().unwrap()))
-
+        .clang_arg(format!("-DMBEDTLS_PSA_CRYPTO_CONFIG_FILE=\"{}\"", mbedcrypto_config.psa_crypto_config_file))
+        .clang_arg(format!("-DMBEDTLS_CONFIG_FILE=\"{}\"", mbedcrypto_config.mbedtls_config_file))
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .use_core()
         .generate()
@@ -57,39 +392,177 @@ fn main() {
         .expect("Couldn't write bindings!");
 
     // Link the built libraries
-    println!("cargo:rustc-link-arg={}/interface/lib/s_veneers.o", out_dir.display());
+    println!("cargo:rustc-link-arg={}/interface/lib/s_veneers.o", dst.display());
 
     // Set environment variables for other parts of the build process
     println!("cargo:rustc-env=TFM_BUILD_DIR={}", dst.display());
     println!("cargo:rustc-env=ARM_TOOLCHAIN_DIR={}", toolchain_dir.display());
 }
 
-fn fetch_tfm(tfm_dir: &PathBuf) {
-    if tfm_dir.exists() {
-        // Try to update existing repository
+/// Configures, builds, and installs TF-M via cmake into `out_dir/tfm-build`,
+/// reusing a cached build keyed on the TF-M revision, toolchain identity,
+/// platform, profile, and test flags when the checkout is pinned
+/// (unpinned/`main`-tracking builds bypass the cache and rerun cmake every time,
+/// since an unpinned TF-M checkout makes the build a moving target).
+fn prepare_tfm_build(
+    out_dir: &PathBuf,
+    tfm_dir: &PathBuf,
+    toolchain_dir: &PathBuf,
+    toolchain_id: &str,
+    venv_dir: &PathBuf,
+    tfm_rev: &str,
+    tfm_unpinned: bool,
+    config: &BuildConfig,
+) -> PathBuf {
+    let build_dir = out_dir.join("tfm-build");
+
+    if tfm_unpinned {
+        run_tfm_cmake_build(&build_dir, tfm_dir, toolchain_dir, venv_dir, config);
+        return build_dir;
+    }
+
+    let key = format!(
+        "tfm-build-{}-{}-{}-{}-test_s_{}-test_s_crypto_{}",
+        sanitize_cache_key_component(tfm_rev),
+        sanitize_cache_key_component(toolchain_id),
+        sanitize_cache_key_component(&config.platform),
+        sanitize_cache_key_component(&config.profile),
+        config.test_s,
+        config.test_s_crypto,
+    );
+
+    // An existing build output is only kept if it resolves to this key's cache
+    // entry; a build for any other configuration is removed and relinked. Where
+    // entries are copied rather than symlinked, the copy is refreshed every run.
+    if build_dir.exists() {
+        if build_dir.canonicalize().ok() == cache_root().join(&key).canonicalize().ok() {
+            println!("TF-M build output already exists, skipping cmake build");
+            return build_dir;
+        }
+        println!("{} holds a TF-M build for another configuration; replacing it", build_dir.display());
+        remove_tfm_dir(&build_dir);
+    }
+
+    let entry_dir = populate_cache_entry(&cache_root(), &key, |dir| {
+        run_tfm_cmake_build(dir, tfm_dir, toolchain_dir, venv_dir, config);
+    });
+    link_cache_entry(&entry_dir, &build_dir);
+
+    println!("TF-M build output ready at {}", build_dir.display());
+    build_dir
+}
+
+/// Runs the actual cmake configure/build/install for TF-M, installing into `dst_dir`.
+fn run_tfm_cmake_build(dst_dir: &PathBuf, tfm_dir: &PathBuf, toolchain_dir: &PathBuf, venv_dir: &PathBuf, config: &BuildConfig) {
+    cmake::Config::new(tfm_dir)
+        .out_dir(dst_dir)
+        .define("TFM_PLATFORM", &config.platform)
+        .define("TFM_PROFILE", &config.profile)
+        .define("TEST_S", if config.test_s { "ON" } else { "OFF" })
+        .define("TEST_S_CRYPTO", if config.test_s_crypto { "ON" } else { "OFF" })
+        .define("CMAKE_C_COMPILER", toolchain_dir.join("bin/arm-none-eabi-gcc"))
+        .define("CMAKE_CXX_COMPILER", toolchain_dir.join("bin/arm-none-eabi-g++"))
+        .define("CMAKE_ASM_COMPILER", toolchain_dir.join("bin/arm-none-eabi-gcc"))
+        .env("VIRTUAL_ENV", venv_dir)
+        .env("PATH", prepend_to_path(&venv_script_dir(venv_dir)))
+        .build_arg("install")
+        .build();
+}
+
+/// Clones or reuses a cached checkout of TF-M at the pinned revision (or tracks
+/// `main` directly, uncached, when `TFM_ALLOW_UNPINNED=1`) into `tfm_dir`.
+/// Returns the revision used and whether it was the unpinned escape hatch, so
+/// callers (like the venv cache) can key their own cache entries off it.
+fn fetch_tfm(tfm_dir: &PathBuf) -> (String, bool) {
+    println!("cargo:rerun-if-env-changed={}", TFM_REV_ENV);
+    println!("cargo:rerun-if-env-changed={}", ALLOW_UNPINNED_ENV);
+
+    let rev = env::var(TFM_REV_ENV).unwrap_or_else(|_| TFM_PINNED_REV.to_string());
+    let unpinned = env::var(ALLOW_UNPINNED_ENV).as_deref() == Ok("1");
+
+    if unpinned {
+        println!("{} set, tracking origin/main instead of {} (not cached)", ALLOW_UNPINNED_ENV, rev);
+        if fs::symlink_metadata(tfm_dir).map(|m| m.is_symlink()).unwrap_or(false) {
+            // `tfm_dir` is a symlink into the shared, content-addressed cache from
+            // an earlier pinned build. Replace it with a private, real checkout
+            // before pulling `main` into it, so we never mutate the cache entry
+            // that other crates/builds on this machine are reusing.
+            println!("Replacing cached TF-M checkout with a private unpinned clone");
+            remove_tfm_dir(tfm_dir);
+        }
+        if !tfm_dir.exists() {
+            clone_tfm(tfm_dir);
+            // `clone_tfm` leaves the working tree empty; populate it from `main`.
+            let status = Command::new("git")
+                .current_dir(tfm_dir)
+                .args(&["checkout", "-f", "main"])
+                .status()
+                .expect("Failed to check out TF-M main");
+            if !status.success() {
+                panic!("Failed to check out TF-M main");
+            }
+        }
         let status = Command::new("git")
             .current_dir(tfm_dir)
             .args(&["pull", "origin", "main"])
             .status()
             .expect("Failed to update TF-M repository");
 
-        if status.success() {
-            println!("TF-M repository updated successfully");
-            return;
+        if !status.success() {
+            panic!("Failed to update TF-M repository");
         }
+        return (rev, unpinned);
     }
 
-    // Clone failed or directory doesn't exist, remove it if it exists
     if tfm_dir.exists() {
-        fs::remove_dir_all(tfm_dir).expect("Failed to remove existing TF-M directory");
+        if checked_out_rev(tfm_dir).as_deref() == Some(rev.as_str()) {
+            return (rev, unpinned);
+        }
+        println!(
+            "{} exists but isn't at pinned revision {} (TFM_REV changed?); re-fetching",
+            tfm_dir.display(), rev,
+        );
+        remove_tfm_dir(tfm_dir);
     }
 
-    // Clone the repository
+    let key = format!("tfm-{}", sanitize_cache_key_component(&rev));
+    let entry_dir = populate_cache_entry(&cache_root(), &key, |dir| {
+        clone_tfm(dir);
+        checkout_tfm_rev(dir, &rev);
+        fs::write(dir.join(TFM_REV_MARKER), &rev).expect("Failed to write TF-M revision marker");
+    });
+    link_cache_entry(&entry_dir, tfm_dir);
+
+    println!("TF-M repository ready at pinned revision {} ({})", rev, tfm_dir.display());
+    (rev, unpinned)
+}
+
+/// Reads back the revision marker `fetch_tfm` wrote into a cached TF-M checkout,
+/// if any. Absence (e.g. a pre-cache-marker checkout) is treated as "unknown" and
+/// forces a re-fetch rather than risking a silently stale tree.
+fn checked_out_rev(tfm_dir: &PathBuf) -> Option<String> {
+    fs::read_to_string(tfm_dir.join(TFM_REV_MARKER)).ok().map(|s| s.trim().to_string())
+}
+
+/// Removes a stale `tfm_dir`, whether it's a symlink into the cache (the common
+/// case) or a real directory (the unpinned-tracking case), without following a
+/// symlink into the cache entry and deleting cached content out from under it.
+fn remove_tfm_dir(tfm_dir: &PathBuf) {
+    let metadata = fs::symlink_metadata(tfm_dir).expect("Failed to stat stale TF-M directory");
+    if metadata.is_symlink() {
+        fs::remove_file(tfm_dir).expect("Failed to remove stale TF-M symlink");
+    } else {
+        fs::remove_dir_all(tfm_dir).expect("Failed to remove stale TF-M directory");
+    }
+}
+
+fn clone_tfm(dest: &PathBuf) {
     let status = Command::new("git")
         .args(&[
             "clone",
+            "--no-checkout",
             "https://git.trustedfirmware.org/TF-M/trusted-firmware-m.git",
-            tfm_dir.to_str().unwrap(),
+            dest.to_str().unwrap(),
         ])
         .status()
         .expect("Failed to clone TF-M repository");
@@ -97,54 +570,345 @@ fn fetch_tfm(tfm_dir: &PathBuf) {
     if !status.success() {
         panic!("Failed to clone TF-M repository");
     }
+}
+
+/// Shallow-fetches and checks out `rev` in `dir` so the TF-M tree is reproducible
+/// regardless of what `main` currently points at.
+fn checkout_tfm_rev(dir: &PathBuf, rev: &str) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(&["fetch", "--depth", "1", "origin", rev])
+        .status()
+        .expect("Failed to fetch pinned TF-M revision");
+
+    if !status.success() {
+        panic!("Failed to fetch TF-M revision {} (set {}=1 to track main instead)", rev, ALLOW_UNPINNED_ENV);
+    }
 
-    println!("TF-M repository cloned successfully")
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(&["checkout", "--detach", "FETCH_HEAD"])
+        .status()
+        .expect("Failed to check out pinned TF-M revision");
+
+    if !status.success() {
+        panic!("Failed to check out TF-M revision {}", rev);
+    }
 }
 
-fn prepare_toolchain(out_dir: &PathBuf, toolchain_dir: &PathBuf) {
+/// Resolves the Arm GNU toolchain directory to use, downloading it if necessary.
+///
+/// If `TFM_ARM_TOOLCHAIN` is set, it is used as-is (after validating the compiler
+/// it points at); otherwise the `TOOLCHAIN_RELEASES` entry for the `HOST` triple
+/// is downloaded into the build cache and linked into `toolchain_dir`.
+///
+/// Returns the toolchain's directory and a cache-key-safe
+/// identity string for it (so callers like the TF-M build cache can key off
+/// exactly which toolchain produced their output).
+fn prepare_toolchain(toolchain_dir: &PathBuf) -> (PathBuf, String) {
+    if let Ok(external_dir) = env::var(EXTERNAL_TOOLCHAIN_ENV) {
+        println!("cargo:rerun-if-env-changed={}", EXTERNAL_TOOLCHAIN_ENV);
+        let external_dir = PathBuf::from(external_dir);
+        let detected = validate_toolchain(&external_dir);
+        return (external_dir, format!("external-{}", detected));
+    }
+
+    let host = env::var("HOST").expect("Cargo did not set HOST");
+    let release = TOOLCHAIN_RELEASES
+        .iter()
+        .find(|r| r.host == host)
+        .unwrap_or_else(|| {
+            let supported: Vec<&str> = TOOLCHAIN_RELEASES.iter().map(|r| r.host).collect();
+            panic!("No Arm GNU Toolchain download known for host {}; supported hosts: {}", host, supported.join(", "))
+        });
+    let identity = format!("{}-{}", release.version, release.host);
+
     if toolchain_dir.exists() {
         println!("Toolchain already exists, skipping download");
-        return;
+        return (toolchain_dir.clone(), identity);
     }
 
-    let archive_path = out_dir.join(TOOLCHAIN_ARCHIVE);
+    let key = format!("toolchain-{}", identity);
+    let entry_dir = populate_cache_entry(&cache_root(), &key, |dir| {
+        download_and_extract_toolchain(dir, release);
+    });
+    link_cache_entry(&entry_dir, toolchain_dir);
+
+    println!("Arm GNU Embedded Toolchain ready at {}", toolchain_dir.display());
+    (toolchain_dir.clone(), identity)
+}
+
+/// Downloads, checksum-verifies, and extracts `release`'s archive directly into
+/// `dest_dir` (an empty staging directory owned by the build cache).
+///
+/// Scratch space (the downloaded archive, the extraction scratch dir) lives
+/// alongside `dest_dir` rather than under `OUT_DIR`, since `OUT_DIR` (Cargo's
+/// target tree) and the cache root are commonly different filesystems and the
+/// final move into `dest_dir` would otherwise risk an `EXDEV` failure.
+fn download_and_extract_toolchain(dest_dir: &PathBuf, release: &ToolchainRelease) {
+    let scratch_root = dest_dir.parent().expect("Cache staging directory has no parent");
+    let archive_name = release.url.rsplit('/').next().expect("Toolchain URL has no file name");
+    let archive_path = scratch_root.join(archive_name);
 
-    // Download the toolchain
-    println!("Downloading Arm GNU Embedded Toolchain...");
-    let mut response = reqwest::blocking::get(TOOLCHAIN_URL).expect("Failed to download toolchain");
+    println!("Downloading Arm GNU Embedded Toolchain for {}...", release.host);
+    let mut response = reqwest::blocking::get(release.url).expect("Failed to download toolchain");
     let mut file = fs::File::create(&archive_path).expect("Failed to create toolchain archive");
     std::io::copy(&mut response, &mut file).expect("Failed to write toolchain archive");
 
-    // Extract the toolchain
+    verify_toolchain_checksum(&archive_path, release);
+
+    // Extract into a scratch directory so we can find the single top-level
+    // directory the archive unpacks to, regardless of its name, then move its
+    // contents into `dest_dir` itself.
     println!("Extracting Arm GNU Embedded Toolchain...");
-    let status = Command::new("tar")
-        .args(&["-xjf", archive_path.to_str().unwrap(), "-C", out_dir.to_str().unwrap()])
-        .status()
-        .expect("Failed to extract toolchain");
+    let extract_dir = scratch_root.join(format!("{}-extracted", archive_name));
+    fs::create_dir_all(&extract_dir).expect("Failed to create toolchain extraction directory");
+    extract_archive(&archive_path, &extract_dir, release.kind);
+
+    let extracted_dir = fs::read_dir(&extract_dir)
+        .expect("Failed to read toolchain extraction directory")
+        .filter_map(|entry| entry.ok())
+        .find(|entry| entry.path().is_dir())
+        .unwrap_or_else(|| panic!("Toolchain archive {} did not contain a directory", archive_name))
+        .path();
+
+    fs::remove_dir(dest_dir).expect("Failed to clear empty cache staging directory");
+    rename_or_copy(&extracted_dir, dest_dir);
+    fs::remove_dir_all(&extract_dir).ok();
+
+    fs::remove_file(archive_path).expect("Failed to remove toolchain archive");
+}
+
+/// Unpacks `archive_path` into `dest_dir` using the extractor appropriate for `kind`.
+fn extract_archive(archive_path: &PathBuf, dest_dir: &PathBuf, kind: ArchiveKind) {
+    let status = match kind {
+        ArchiveKind::TarBz2 => Command::new("tar")
+            .args(&["-xjf", archive_path.to_str().unwrap(), "-C", dest_dir.to_str().unwrap()])
+            .status()
+            .expect("Failed to run tar"),
+        ArchiveKind::Zip => {
+            let file = fs::File::open(archive_path).expect("Failed to open zip archive");
+            let mut archive = zip::ZipArchive::new(file).expect("Failed to read zip archive");
+            archive.extract(dest_dir).expect("Failed to extract zip archive");
+            return;
+        }
+    };
 
     if !status.success() {
-        panic!("Failed to extract toolchain");
+        panic!("Failed to extract toolchain archive {}", archive_path.display());
     }
+}
 
-    // Rename the extracted directory to our standard name
-    let extracted_dir = out_dir.join("gcc-arm-none-eabi-10.3-2021.10");
-    fs::rename(extracted_dir, toolchain_dir).expect("Failed to rename toolchain directory");
+/// Verifies `archive_path` against `release`'s known-good digest, panicking on a
+/// mismatch so a tampered or corrupted download is never extracted.
+fn verify_toolchain_checksum(archive_path: &PathBuf, release: &ToolchainRelease) {
+    if env::var(ALLOW_UNPINNED_ENV).as_deref() == Ok("1") {
+        println!("{} set, skipping toolchain checksum verification", ALLOW_UNPINNED_ENV);
+        return;
+    }
 
-    // Clean up the archive
-    fs::remove_file(archive_path).expect("Failed to remove toolchain archive");
+    let bytes = fs::read(archive_path).expect("Failed to read downloaded toolchain archive for checksum verification");
+    let digest = Sha256::digest(&bytes);
+    let actual = digest.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+
+    if actual != release.sha256 {
+        panic!(
+            "Checksum mismatch for {} toolchain archive: expected {}, got {} (set {}=1 to bypass)",
+            release.host, release.sha256, actual, ALLOW_UNPINNED_ENV,
+        );
+    }
+}
+
+/// Runs `arm-none-eabi-gcc -v` from `toolchain_dir/bin`, panicking unless its
+/// reported version is at least [`DEFAULT_MIN_TOOLCHAIN_VERSION`] (or the version
+/// named by `TFM_ARM_TOOLCHAIN_MIN_VERSION`), and returns the detected version.
+fn validate_toolchain(toolchain_dir: &PathBuf) -> ToolchainVersion {
+    let gcc = toolchain_dir.join("bin/arm-none-eabi-gcc");
+    let output = Command::new(&gcc)
+        .arg("-v")
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to run {} -v: {}", gcc.display(), e));
+
+    // GCC prints its version banner to stderr, not stdout.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let last_line = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_else(|| panic!("{} -v produced no stderr output to parse a version from", gcc.display()));
+
+    let detected = parse_toolchain_version(last_line)
+        .unwrap_or_else(|| panic!("Couldn't parse a toolchain version out of: {:?}", last_line));
+
+    let min_version = match env::var(MIN_TOOLCHAIN_VERSION_ENV) {
+        Ok(v) => parse_version_string(&v).unwrap_or_else(|| {
+            panic!("{} must be formatted as YYYY.MM or X.Y, got {:?}", MIN_TOOLCHAIN_VERSION_ENV, v)
+        }),
+        Err(_) => DEFAULT_MIN_TOOLCHAIN_VERSION,
+    };
+    println!("cargo:rerun-if-env-changed={}", MIN_TOOLCHAIN_VERSION_ENV);
+
+    if detected < min_version {
+        panic!(
+            "External toolchain at {} reports version {}, but at least {} is required (see {})",
+            toolchain_dir.display(), detected, min_version, MIN_TOOLCHAIN_VERSION_ENV,
+        );
+    }
+
+    println!("Using external Arm toolchain at {} (version {})", toolchain_dir.display(), detected);
+    detected
+}
+
+/// Extracts a toolchain version from a line of `arm-none-eabi-gcc -v` output,
+/// recognizing both the legacy `YYYY.MM` scheme (e.g. `2021.10` out of
+/// `... 10.3-2021.10)`) and the `X.Y[.RelZ]` scheme used by releases since (e.g.
+/// `12.2` out of `... 12.2.Rel1)`). Legacy matches take priority over semantic
+/// ones so a legacy banner's `10.3` (gcc's own version, not the release version)
+/// doesn't shadow its real `2021.10` release token.
+fn parse_toolchain_version(line: &str) -> Option<ToolchainVersion> {
+    let tokens: Vec<&str> = line.split(|c: char| !c.is_ascii_digit() && c != '.').filter(|t| !t.is_empty()).collect();
+
+    if let Some((year, month)) = tokens.iter().find_map(|t| parse_legacy_version(t)) {
+        return Some(ToolchainVersion::Legacy(year, month));
+    }
+    tokens.iter().find_map(|t| parse_semantic_version(t)).map(|(major, minor)| ToolchainVersion::Semantic(major, minor))
+}
+
+/// Parses a standalone version string (as given via [`MIN_TOOLCHAIN_VERSION_ENV`]),
+/// trying the legacy `YYYY.MM` scheme before falling back to `X.Y[.RelZ]`.
+fn parse_version_string(token: &str) -> Option<ToolchainVersion> {
+    parse_legacy_version(token)
+        .map(|(year, month)| ToolchainVersion::Legacy(year, month))
+        .or_else(|| parse_semantic_version(token).map(|(major, minor)| ToolchainVersion::Semantic(major, minor)))
+}
+
+/// Parses a bare `YYYY.MM` string into its numeric parts.
+fn parse_legacy_version(token: &str) -> Option<(u32, u32)> {
+    let (year, month) = token.split_once('.')?;
+    let year: u32 = year.parse().ok()?;
+    let month: u32 = month.parse().ok()?;
+    if year >= 2000 && year < 3000 && month >= 1 && month <= 12 {
+        Some((year, month))
+    } else {
+        None
+    }
+}
+
+/// Parses the leading `major.minor` out of an `X.Y` or `X.Y.RelZ`-style string
+/// (the `.RelZ`/patch suffix, if any, is ignored).
+fn parse_semantic_version(token: &str) -> Option<(u32, u32)> {
+    let mut parts = token.split('.');
+    let major: u32 = parts.next()?.parse().ok()?;
+    let minor: u32 = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
 
-    println!("Arm GNU Embedded Toolchain prepared successfully");
+/// Env vars that can override Python interpreter discovery, checked in order.
+const PYTHON_OVERRIDE_ENVS: &[&str] = &["TFM_PYTHON", "PYTHON_SYS_EXECUTABLE"];
+
+/// Resolves the Python interpreter to create the venv with: an explicit override
+/// from [`PYTHON_OVERRIDE_ENVS`] if set, otherwise the first of `python3`/`python`
+/// found on `PATH`. Panics with the full list of what was searched if neither works.
+fn resolve_python_interpreter() -> PathBuf {
+    for var in PYTHON_OVERRIDE_ENVS {
+        println!("cargo:rerun-if-env-changed={}", var);
+        if let Ok(path) = env::var(var) {
+            let path = PathBuf::from(path);
+            return path.canonicalize().unwrap_or_else(|e| panic!("{}={} is not a valid interpreter path: {}", var, path.display(), e));
+        }
+    }
+
+    let candidates = ["python3", "python"];
+    let path_var = env::var_os("PATH").unwrap_or_default();
+    let search_dirs: Vec<PathBuf> = env::split_paths(&path_var).collect();
+
+    for candidate in candidates {
+        for dir in &search_dirs {
+            let exe_name = if cfg!(windows) { format!("{}.exe", candidate) } else { candidate.to_string() };
+            let full_path = dir.join(exe_name);
+            if full_path.is_file() {
+                return full_path.canonicalize().unwrap_or(full_path);
+            }
+        }
+    }
+
+    panic!(
+        "Couldn't find a Python interpreter: searched for {:?} in {} PATH director{} ({:?}); set {} to an explicit interpreter path",
+        candidates,
+        search_dirs.len(),
+        if search_dirs.len() == 1 { "y" } else { "ies" },
+        search_dirs,
+        PYTHON_OVERRIDE_ENVS[0],
+    );
+}
+
+/// The subdirectory a venv puts its executables in: `Scripts` on Windows, `bin`
+/// everywhere else.
+fn venv_script_dir(venv_dir: &PathBuf) -> PathBuf {
+    if cfg!(windows) {
+        venv_dir.join("Scripts")
+    } else {
+        venv_dir.join("bin")
+    }
+}
+
+/// Prepends `dir` to the current process's `PATH`, joining entries with the
+/// platform-appropriate separator (`;` on Windows, `:` elsewhere) instead of
+/// assuming Unix-style PATH syntax.
+fn prepend_to_path(dir: &PathBuf) -> std::ffi::OsString {
+    let existing = env::var_os("PATH").unwrap_or_default();
+    let entries = std::iter::once(dir.clone()).chain(env::split_paths(&existing));
+    env::join_paths(entries).expect("Failed to join PATH entries")
 }
 
-fn prepare_python_venv(venv_dir: &PathBuf, tfm_dir: &PathBuf) {
+/// Prepares the Python venv into `venv_dir`, reusing a cached one keyed on
+/// `tfm_rev` when available. Skips the cache entirely when `tfm_unpinned` is set,
+/// since an unpinned TF-M checkout makes `tools/requirements.txt` a moving target.
+fn prepare_python_venv(venv_dir: &PathBuf, tfm_dir: &PathBuf, tfm_rev: &str, tfm_unpinned: bool) {
+    // A pinned build only keeps an existing venv that resolves to `tfm_rev`'s cache entry.
+    if !tfm_unpinned && venv_dir.exists() {
+        let wanted = cache_root().join(format!("venv-{}", sanitize_cache_key_component(tfm_rev)));
+        if venv_dir.canonicalize().ok() != wanted.canonicalize().ok() {
+            println!("{} isn't the cached venv for TF-M revision {}; replacing it", venv_dir.display(), tfm_rev);
+            if fs::symlink_metadata(venv_dir).map(|m| m.is_symlink()).unwrap_or(false) {
+                fs::remove_file(venv_dir).expect("Failed to remove stale venv symlink");
+            } else {
+                fs::remove_dir_all(venv_dir).expect("Failed to remove stale venv directory");
+            }
+        }
+    }
+
     if venv_dir.exists() {
         println!("Python virtual environment already exists, skipping creation");
         return;
     }
 
-    // Create virtual environment
-    println!("Creating Python virtual environment...");
-    let status = Command::new("python3")
+    if tfm_unpinned {
+        create_venv(venv_dir, tfm_dir);
+        return;
+    }
+
+    let key = format!("venv-{}", sanitize_cache_key_component(tfm_rev));
+    let entry_dir = populate_cache_entry(&cache_root(), &key, |dir| {
+        create_venv(dir, tfm_dir);
+    });
+    link_cache_entry(&entry_dir, venv_dir);
+
+    println!("Python virtual environment ready at {}", venv_dir.display());
+}
+
+/// Creates a venv at `venv_dir` and installs TF-M's `tools/requirements.txt` into it.
+fn create_venv(venv_dir: &PathBuf, tfm_dir: &PathBuf) {
+    let python = resolve_python_interpreter();
+    let script_dir = venv_script_dir(venv_dir);
+
+    // `python -m venv` wants its target to not already exist.
+    if venv_dir.exists() {
+        fs::remove_dir(venv_dir).expect("Failed to clear empty cache staging directory");
+    }
+
+    println!("Creating Python virtual environment with {}...", python.display());
+    let status = Command::new(&python)
         .args(&["-m", "venv", venv_dir.to_str().unwrap()])
         .status()
         .expect("Failed to create Python virtual environment");
@@ -156,10 +920,11 @@ fn prepare_python_venv(venv_dir: &PathBuf, tfm_dir: &PathBuf) {
     // Activate virtual environment and install requirements
     println!("Installing Python dependencies...");
     let requirements_file = tfm_dir.join("tools/requirements.txt");
-    let status = Command::new(venv_dir.join("bin/pip"))
+    let pip = if cfg!(windows) { script_dir.join("pip.exe") } else { script_dir.join("pip") };
+    let status = Command::new(pip)
         .args(&["install", "-r", requirements_file.to_str().unwrap()])
         .env("VIRTUAL_ENV", venv_dir)
-        .env("PATH", format!("{}:{}", venv_dir.join("bin").display(), env::var("PATH").unwrap()))
+        .env("PATH", prepend_to_path(&script_dir))
         .status()
         .expect("Failed to install Python dependencies");
 